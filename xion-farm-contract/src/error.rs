@@ -0,0 +1,68 @@
+use cosmwasm_std::{DivideByZeroError, OverflowError, StdError, Uint128};
+use thiserror::Error;
+
+// Erros específicos do contrato, usados pelos handlers de execução em vez de
+// `StdError::generic_err`, para que overflow, divisão por zero e violações de
+// regras de negócio sejam distinguíveis pelo chamador
+#[derive(Error, Debug, PartialEq)]
+pub enum ContractError {
+    #[error("{0}")]
+    Std(#[from] StdError),
+
+    #[error("{0}")]
+    Overflow(#[from] OverflowError),
+
+    #[error("{0}")]
+    DivideByZero(#[from] DivideByZeroError),
+
+    #[error("Arithmetic overflow")]
+    CountOverflow {},
+
+    #[error("Not enough stock: requested {requested}, available {available}")]
+    InsufficientStock { requested: u64, available: u64 },
+
+    #[error("Product already sold")]
+    ProductAlreadySold {},
+
+    #[error("Must send exactly {amount}{denom}")]
+    WrongPayment { amount: Uint128, denom: String },
+
+    #[error("Product is not a campaign")]
+    NotACampaign {},
+
+    #[error("Product is a crowdfunding campaign; use Fund instead of Buy")]
+    ProductIsACampaign {},
+
+    #[error("Campaign funding deadline has passed")]
+    DeadlinePassed {},
+
+    #[error("Campaign funding deadline has not passed yet")]
+    DeadlineNotReached {},
+
+    #[error("Campaign goal was not met")]
+    GoalNotMet {},
+
+    #[error("Campaign goal was met, no refunds are due")]
+    GoalMet {},
+
+    #[error("Campaign already claimed")]
+    AlreadyClaimed {},
+
+    #[error("Only the owner can claim this campaign")]
+    Unauthorized {},
+
+    #[error("No contribution found for this address")]
+    NoContribution {},
+
+    #[error("Quantity does not match this group's target quantity")]
+    QuantityMismatch {},
+
+    #[error("No group purchase in progress for this product")]
+    NoGroupInProgress {},
+
+    #[error("Group has no participants")]
+    EmptyGroup {},
+
+    #[error("{participant} has not deposited their full share")]
+    ShareNotDeposited { participant: String },
+}
@@ -1,14 +1,29 @@
 use cosmwasm_std::{
-    to_binary, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdResult, Coin, StdError,
-    entry_point,
+    to_binary, Addr, BankMsg, Binary, Deps, DepsMut, Env, MessageInfo, Order, Response, StdResult,
+    Coin, StdError, Uint128, entry_point,
 };
+use cw2::{get_contract_version, set_contract_version};
+use semver::Version;
 use serde::{Deserialize, Serialize};
-use cw_storage_plus::{Item, Map};
+use cw_storage_plus::{Bound, Index, IndexList, IndexedMap, Item, Map, MultiIndex};
+
+mod error;
+mod math;
+
+pub use error::ContractError;
+
+// Nome e versão do contrato, rastreados pelo cw2 para permitir migrações seguras
+const CONTRACT_NAME: &str = "crates.io:xion-farm-contract";
+const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
 // Mensagem enviada para inicializar o contrato
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub struct InitMsg {}
 
+// Mensagem enviada para migrar o contrato para uma nova versão
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct MigrateMsg {}
+
 // Mensagens que podem ser enviadas ao contrato
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 #[serde(rename_all = "snake_case")]
@@ -17,19 +32,66 @@ pub enum ExecuteMsg {
         product_name: String,
         product_price: Coin,
         product_quantity: u64,
+        // Se presente, o produto vira uma campanha (pre-sale) que só é
+        // entregue quando `goal` é atingido antes de `deadline`
+        campaign: Option<CampaignInit>,
     },
     Buy {
         product_id: String,
         quantity: u64,
     },
+    Fund {
+        product_id: String,
+        quantity: u64,
+    },
+    Claim {
+        product_id: String,
+    },
+    Refund {
+        product_id: String,
+    },
+    JoinPurchase {
+        product_id: String,
+        quantity: u64,
+    },
+    SettleGroup {
+        product_id: String,
+    },
+}
+
+// Parâmetros informados pelo vendedor para abrir uma campanha
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct CampaignInit {
+    pub goal: Uint128,
+    pub deadline: u64,
 }
 
 // Mensagens que podem consultar o contrato
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 #[serde(rename_all = "snake_case")]
 pub enum QueryMsg {
-    GetProducts {},
+    GetProducts {
+        start_after: Option<String>,
+        limit: Option<u32>,
+        status: Option<ProductStatus>,
+        owner: Option<String>,
+    },
+    // As duas consultas abaixo usam os índices secundários de `PRODUCTS`
+    // em vez de varrer o catálogo inteiro
+    GetProductsByOwner {
+        owner: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    GetProductsByStatus {
+        status: ProductStatus,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
     GetProduct { id: String },
+    GetFunders { product_id: String },
+    GetFunds { product_id: String },
+    GetGroup { product_id: String },
 }
 
 // Definir o modelo de Produto
@@ -41,6 +103,21 @@ pub struct Product {
     pub price: Coin,
     pub owner: String,
     pub status: ProductStatus,
+    // `default` permite carregar produtos salvos antes do campo existir
+    #[serde(default)]
+    pub campaign: Option<Campaign>,
+}
+
+// Estado de uma campanha de pre-sale: só entrega se `raised` atingir `goal`
+// antes de `deadline` (altura de bloco)
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Campaign {
+    pub goal: Uint128,
+    pub deadline: u64,
+    pub raised: Uint128,
+    // Unidades já financiadas, usado para limitar `Fund` ao estoque disponível
+    #[serde(default)]
+    pub funded_units: u64,
 }
 
 // Status do Produto (Disponível ou Vendido)
@@ -56,14 +133,105 @@ pub struct State {
     pub total_products: u64,
 }
 
+// Paginação padrão e máxima para GetProducts
+const DEFAULT_LIMIT: u32 = 10;
+const MAX_LIMIT: u32 = 30;
+
+// Índices secundários de PRODUCTS, para consultar por dono ou por status
+// sem varrer o catálogo inteiro
+pub struct ProductIndexes<'a> {
+    pub owner: MultiIndex<'a, (Vec<u8>, Vec<u8>), Product>,
+    pub status: MultiIndex<'a, (Vec<u8>, Vec<u8>), Product>,
+}
+
+impl<'a> IndexList<Product> for ProductIndexes<'a> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<Product>> + '_> {
+        let v: Vec<&dyn Index<Product>> = vec![&self.owner, &self.status];
+        Box::new(v.into_iter())
+    }
+}
+
+// Codifica o status do produto como bytes, usado como valor do índice secundário
+fn product_status_bytes(status: &ProductStatus) -> Vec<u8> {
+    match status {
+        ProductStatus::Available => b"available".to_vec(),
+        ProductStatus::Sold => b"sold".to_vec(),
+    }
+}
+
+fn products<'a>() -> IndexedMap<'a, &'a str, Product, ProductIndexes<'a>> {
+    let indexes = ProductIndexes {
+        owner: MultiIndex::new(
+            |product, pk| (product.owner.as_bytes().to_vec(), pk),
+            "products",
+            "products__owner",
+        ),
+        status: MultiIndex::new(
+            |product, pk| (product_status_bytes(&product.status), pk),
+            "products",
+            "products__status",
+        ),
+    };
+    IndexedMap::new("products", indexes)
+}
+
 // Definir armazenamento
 const STATE: Item<State> = Item::new("state");
-const PRODUCTS: Map<&str, Product> = Map::new("products");
+// Contribuições de cada financiador em uma campanha, por (product_id, endereço)
+const FUNDS: Map<(&str, &Addr), Coin> = Map::new("funds");
+// Compra em grupo em andamento para um produto (quem entrou e a quantidade combinada)
+const GROUPS: Map<&str, GroupPurchase> = Map::new("groups");
+// Depósito de cada participante de uma compra em grupo, por (product_id, endereço)
+const GROUP_DEPOSITS: Map<(&str, &Addr), Coin> = Map::new("group_deposits");
+
+// Compra em grupo: vários endereços dividem o custo de uma única compra.
+// O primeiro a entrar é o iniciador e recebe o resto da divisão inteira.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct GroupPurchase {
+    pub quantity: u64,
+    pub participants: Vec<Addr>,
+}
 
 // Resposta de consulta para produtos
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub struct ProductsResponse {
     pub products: Vec<Product>,
+    // Id do último produto retornado, para continuar a paginação em `start_after`
+    pub last_key: Option<String>,
+}
+
+// Resposta de consulta para os financiadores de uma campanha
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct FundersResponse {
+    pub funders: Vec<FunderShare>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct FunderShare {
+    pub address: String,
+    pub amount: Coin,
+}
+
+// Resposta de consulta para o total arrecadado em uma campanha
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct FundsResponse {
+    pub goal: Uint128,
+    pub raised: Uint128,
+    pub deadline: u64,
+}
+
+// Resposta de consulta para uma compra em grupo
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct GroupResponse {
+    pub quantity: u64,
+    pub participants: Vec<GroupParticipantShare>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct GroupParticipantShare {
+    pub address: String,
+    pub share_owed: Uint128,
+    pub deposited: Uint128,
 }
 
 #[entry_point]
@@ -73,31 +241,87 @@ pub fn instantiate(
     _info: MessageInfo,
     _msg: InitMsg,
 ) -> StdResult<Response> {
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
     let state = State {
         total_products: 0,
     };
     STATE.save(deps.storage, &state)?;
-    
+
     Ok(Response::new().add_attribute("action", "instantiate"))
 }
 
+#[entry_point]
+pub fn migrate(deps: DepsMut, _env: Env, _msg: MigrateMsg) -> StdResult<Response> {
+    let stored = get_contract_version(deps.storage)?;
+
+    if stored.contract != CONTRACT_NAME {
+        return Err(StdError::generic_err(format!(
+            "Cannot migrate from a different contract: {}",
+            stored.contract
+        )));
+    }
+
+    let stored_version: Version = stored
+        .version
+        .parse()
+        .map_err(|_| StdError::generic_err("Invalid stored contract version"))?;
+    let new_version: Version = CONTRACT_VERSION
+        .parse()
+        .map_err(|_| StdError::generic_err("Invalid contract version"))?;
+
+    if new_version < stored_version {
+        return Err(StdError::generic_err(format!(
+            "Cannot migrate from version {} down to {}",
+            stored_version, new_version
+        )));
+    }
+
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "migrate")
+        .add_attribute("from_version", stored_version.to_string())
+        .add_attribute("to_version", new_version.to_string()))
+}
+
 #[entry_point]
 pub fn execute(
     deps: DepsMut,
     env: Env,
     info: MessageInfo,
     msg: ExecuteMsg,
-) -> StdResult<Response> {
+) -> Result<Response, ContractError> {
     match msg {
         ExecuteMsg::RegisterProduct {
             product_name,
             product_price,
             product_quantity,
-        } => execute_register_product(deps, env, info, product_name, product_price, product_quantity),
+            campaign,
+        } => execute_register_product(
+            deps,
+            env,
+            info,
+            product_name,
+            product_price,
+            product_quantity,
+            campaign,
+        ),
         ExecuteMsg::Buy {
             product_id,
             quantity,
         } => execute_buy(deps, env, info, product_id, quantity),
+        ExecuteMsg::Fund {
+            product_id,
+            quantity,
+        } => execute_fund(deps, env, info, product_id, quantity),
+        ExecuteMsg::Claim { product_id } => execute_claim(deps, env, info, product_id),
+        ExecuteMsg::Refund { product_id } => execute_refund(deps, env, info, product_id),
+        ExecuteMsg::JoinPurchase {
+            product_id,
+            quantity,
+        } => execute_join_purchase(deps, env, info, product_id, quantity),
+        ExecuteMsg::SettleGroup { product_id } => execute_settle_group(deps, env, info, product_id),
     }
 }
 
@@ -108,11 +332,19 @@ pub fn execute_register_product(
     product_name: String,
     product_price: Coin,
     product_quantity: u64,
-) -> StdResult<Response> {
+    campaign: Option<CampaignInit>,
+) -> Result<Response, ContractError> {
     let mut state = STATE.load(deps.storage)?;
-    
+
     // Criar um novo produto
-    let product_id = format!("product-{}", state.total_products + 1);
+    let next_total = state
+        .total_products
+        .checked_add(1)
+        .ok_or(ContractError::CountOverflow {})?;
+    // Preenchido com zeros para que a ordenação lexicográfica usada por
+    // Map/IndexedMap coincida com a ordem numérica de criação, o que a
+    // paginação por `start_after`/`last_key` depende
+    let product_id = format!("product-{:010}", next_total);
     let new_product = Product {
         id: product_id.clone(),
         name: product_name,
@@ -120,15 +352,21 @@ pub fn execute_register_product(
         price: product_price,
         owner: info.sender.to_string(),
         status: ProductStatus::Available,
+        campaign: campaign.map(|c| Campaign {
+            goal: c.goal,
+            deadline: c.deadline,
+            raised: Uint128::zero(),
+            funded_units: 0,
+        }),
     };
-    
+
     // Salvar o produto no armazenamento
-    PRODUCTS.save(deps.storage, &product_id, &new_product)?;
-    
+    products().save(deps.storage, &product_id, &new_product)?;
+
     // Atualizar total de produtos
-    state.total_products += 1;
+    state.total_products = next_total;
     STATE.save(deps.storage, &state)?;
-    
+
     Ok(Response::new()
         .add_attribute("action", "register_product")
         .add_attribute("product_id", product_id))
@@ -137,213 +375,1394 @@ pub fn execute_register_product(
 pub fn execute_buy(
     deps: DepsMut,
     _env: Env,
-    _info: MessageInfo,
+    info: MessageInfo,
     product_id: String,
     quantity: u64,
-) -> StdResult<Response> {
+) -> Result<Response, ContractError> {
     // Carregar produto com base no ID
-    let mut product = PRODUCTS.load(deps.storage, &product_id)?;
-    
+    let mut product = products().load(deps.storage, &product_id)?;
+
+    // Produtos em modo campanha só são vendidos via Fund/Claim, nunca por
+    // compra direta
+    if product.campaign.is_some() {
+        return Err(ContractError::ProductIsACampaign {});
+    }
+
     if product.status == ProductStatus::Sold {
-        return Err(StdError::generic_err("Product already sold"));
+        return Err(ContractError::ProductAlreadySold {});
     }
-    
+
     if product.quantity < quantity {
-        return Err(StdError::generic_err("Not enough stock"));
+        return Err(ContractError::InsufficientStock {
+            requested: quantity,
+            available: product.quantity,
+        });
+    }
+
+    // Calcular o valor total devido, protegendo contra overflow
+    let total_due = math::mul(product.price.amount, Uint128::from(quantity))?;
+
+    // Verificar se o comprador enviou exatamente o valor esperado, no denom correto
+    let paid = info
+        .funds
+        .iter()
+        .find(|coin| coin.denom == product.price.denom)
+        .map(|coin| coin.amount)
+        .unwrap_or(Uint128::zero());
+
+    if paid != total_due {
+        return Err(ContractError::WrongPayment {
+            amount: total_due,
+            denom: product.price.denom.clone(),
+        });
     }
-    
+
     // Subtrair a quantidade comprada
-    product.quantity -= quantity;
-    
+    product.quantity = product.quantity.checked_sub(quantity).ok_or(
+        ContractError::InsufficientStock {
+            requested: quantity,
+            available: product.quantity,
+        },
+    )?;
+
     if product.quantity == 0 {
         product.status = ProductStatus::Sold;
     }
-    
+
     // Atualizar o estado do produto
-    PRODUCTS.save(deps.storage, &product_id, &product)?;
-    
+    products().save(deps.storage, &product_id, &product)?;
+
+    // Repassar o pagamento coletado ao dono do produto
+    let payment_msg = BankMsg::Send {
+        to_address: product.owner.clone(),
+        amount: vec![Coin {
+            denom: product.price.denom.clone(),
+            amount: total_due,
+        }],
+    };
+
     Ok(Response::new()
+        .add_message(payment_msg)
         .add_attribute("action", "buy")
         .add_attribute("product_id", product_id)
-        .add_attribute("quantity", quantity.to_string()))
+        .add_attribute("quantity", quantity.to_string())
+        .add_attribute("total_paid", total_due.to_string()))
 }
 
-#[entry_point]
-pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
-    match msg {
-        QueryMsg::GetProducts {} => query_products(deps),
-        QueryMsg::GetProduct { id } => query_product(deps, id),
+pub fn execute_fund(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    product_id: String,
+    quantity: u64,
+) -> Result<Response, ContractError> {
+    let mut product = products().load(deps.storage, &product_id)?;
+
+    let mut campaign = product
+        .campaign
+        .clone()
+        .ok_or(ContractError::NotACampaign {})?;
+
+    if env.block.height >= campaign.deadline {
+        return Err(ContractError::DeadlinePassed {});
     }
-}
 
-pub fn query_products(deps: Deps) -> StdResult<Binary> {
-    let products: StdResult<Vec<_>> = PRODUCTS
-        .range(deps.storage, None, None, cosmwasm_std::Order::Ascending)
-        .map(|item| {
-            let (_, product) = item?;
-            Ok(product)
-        })
-        .collect();
-    
-    to_binary(&ProductsResponse {
-        products: products?,
-    })
-}
+    // Não permitir financiar além do estoque disponível para a campanha
+    let funded_units = campaign
+        .funded_units
+        .checked_add(quantity)
+        .ok_or(ContractError::CountOverflow {})?;
+    if funded_units > product.quantity {
+        return Err(ContractError::InsufficientStock {
+            requested: funded_units,
+            available: product.quantity,
+        });
+    }
 
-pub fn query_product(deps: Deps, id: String) -> StdResult<Binary> {
-    let product = PRODUCTS.load(deps.storage, &id)?;
-    to_binary(&product)
-}
+    // Calcular o valor devido pela quantidade financiada
+    let total_due = math::mul(product.price.amount, Uint128::from(quantity))?;
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
-    use cosmwasm_std::{coins, from_binary};
+    let paid = info
+        .funds
+        .iter()
+        .find(|coin| coin.denom == product.price.denom)
+        .map(|coin| coin.amount)
+        .unwrap_or(Uint128::zero());
 
-    #[test]
-    fn proper_initialization() {
-        let mut deps = mock_dependencies(&[]);
+    if paid != total_due {
+        return Err(ContractError::WrongPayment {
+            amount: total_due,
+            denom: product.price.denom.clone(),
+        });
+    }
 
-        let msg = InitMsg {};
-        let info = mock_info("creator", &coins(1000, "earth"));
+    // Acumular a contribuição deste financiador
+    let previous = FUNDS
+        .may_load(deps.storage, (product_id.as_str(), &info.sender))?
+        .map(|coin| coin.amount)
+        .unwrap_or(Uint128::zero());
+    let contributed = math::add(previous, total_due)?;
+    FUNDS.save(
+        deps.storage,
+        (product_id.as_str(), &info.sender),
+        &Coin {
+            denom: product.price.denom.clone(),
+            amount: contributed,
+        },
+    )?;
 
-        // we can just call .unwrap() to assert this was a success
-        let res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
-        assert_eq!(0, res.messages.len());
+    // Atualizar o total arrecadado e as unidades financiadas da campanha
+    campaign.raised = math::add(campaign.raised, total_due)?;
+    campaign.funded_units = funded_units;
+    product.campaign = Some(campaign);
+    products().save(deps.storage, &product_id, &product)?;
 
-        // Verificar se o estado foi inicializado corretamente
-        let state = STATE.load(&deps.storage).unwrap();
-        assert_eq!(state.total_products, 0);
-    }
+    Ok(Response::new()
+        .add_attribute("action", "fund")
+        .add_attribute("product_id", product_id)
+        .add_attribute("quantity", quantity.to_string())
+        .add_attribute("total_paid", total_due.to_string()))
+}
 
-    #[test]
-    fn register_and_query_product() {
-        let mut deps = mock_dependencies(&[]);
+pub fn execute_claim(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    product_id: String,
+) -> Result<Response, ContractError> {
+    let mut product = products().load(deps.storage, &product_id)?;
 
-        // Inicializar o contrato
-        let msg = InitMsg {};
-        let info = mock_info("creator", &coins(1000, "earth"));
-        let _res = instantiate(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+    if info.sender != product.owner {
+        return Err(ContractError::Unauthorized {});
+    }
 
-        // Registrar um produto
-        let product_name = "Tomato".to_string();
-        let product_price = Coin {
-            denom: "earth".to_string(),
-            amount: 50u128.into(),
-        };
-        let product_quantity = 100u64;
+    if product.status == ProductStatus::Sold {
+        return Err(ContractError::AlreadyClaimed {});
+    }
 
-        let msg = ExecuteMsg::RegisterProduct {
-            product_name: product_name.clone(),
-            product_price: product_price.clone(),
-            product_quantity,
-        };
-        let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    let campaign = product
+        .campaign
+        .clone()
+        .ok_or(ContractError::NotACampaign {})?;
 
-        // Verificar se o produto foi registrado
-        let res = query(deps.as_ref(), mock_env(), QueryMsg::GetProducts {}).unwrap();
-        let products: ProductsResponse = from_binary(&res).unwrap();
-        
-        assert_eq!(products.products.len(), 1);
-        assert_eq!(products.products[0].name, product_name);
-        assert_eq!(products.products[0].price, product_price);
-        assert_eq!(products.products[0].quantity, product_quantity);
-        assert_eq!(products.products[0].status, ProductStatus::Available);
+    if env.block.height < campaign.deadline {
+        return Err(ContractError::DeadlineNotReached {});
     }
 
-    #[test]
-    fn buy_product() {
-        let mut deps = mock_dependencies(&[]);
+    if campaign.raised < campaign.goal {
+        return Err(ContractError::GoalNotMet {});
+    }
 
-        // Inicializar o contrato
-        let msg = InitMsg {};
-        let info = mock_info("creator", &coins(1000, "earth"));
-        let _res = instantiate(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+    // Retirar do estoque as unidades entregues pela campanha
+    product.quantity = product.quantity.checked_sub(campaign.funded_units).ok_or(
+        ContractError::InsufficientStock {
+            requested: campaign.funded_units,
+            available: product.quantity,
+        },
+    )?;
+    product.status = ProductStatus::Sold;
+    products().save(deps.storage, &product_id, &product)?;
 
-        // Registrar um produto
-        let product_name = "Tomato".to_string();
-        let product_price = Coin {
-            denom: "earth".to_string(),
-            amount: 50u128.into(),
-        };
-        let product_quantity = 100u64;
+    let payment_msg = BankMsg::Send {
+        to_address: product.owner.clone(),
+        amount: vec![Coin {
+            denom: product.price.denom.clone(),
+            amount: campaign.raised,
+        }],
+    };
 
-        let msg = ExecuteMsg::RegisterProduct {
-            product_name,
-            product_price,
-            product_quantity,
-        };
-        let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    Ok(Response::new()
+        .add_message(payment_msg)
+        .add_attribute("action", "claim")
+        .add_attribute("product_id", product_id)
+        .add_attribute("total_paid", campaign.raised.to_string()))
+}
 
-        // Comprar o produto
-        let buyer_info = mock_info("buyer", &coins(50, "earth"));
-        let buy_quantity = 30u64;
-        let msg = ExecuteMsg::Buy {
-            product_id: "product-1".to_string(),
-            quantity: buy_quantity,
-        };
-        let _res = execute(deps.as_mut(), mock_env(), buyer_info, msg).unwrap();
+pub fn execute_refund(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    product_id: String,
+) -> Result<Response, ContractError> {
+    let product = products().load(deps.storage, &product_id)?;
 
-        // Verificar se a quantidade foi atualizada
-        let res = query(
-            deps.as_ref(),
-            mock_env(),
-            QueryMsg::GetProduct {
-                id: "product-1".to_string(),
-            },
-        )
-        .unwrap();
-        let product: Product = from_binary(&res).unwrap();
-        
-        assert_eq!(product.quantity, product_quantity - buy_quantity);
-        assert_eq!(product.status, ProductStatus::Available);
-    }
+    let campaign = product
+        .campaign
+        .clone()
+        .ok_or(ContractError::NotACampaign {})?;
 
-    #[test]
-    fn buy_all_stock() {
-        let mut deps = mock_dependencies(&[]);
+    if env.block.height < campaign.deadline {
+        return Err(ContractError::DeadlineNotReached {});
+    }
 
-        // Inicializar o contrato
-        let msg = InitMsg {};
-        let info = mock_info("creator", &coins(1000, "earth"));
-        let _res = instantiate(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+    if campaign.raised >= campaign.goal {
+        return Err(ContractError::GoalMet {});
+    }
 
-        // Registrar um produto
-        let product_name = "Tomato".to_string();
-        let product_price = Coin {
-            denom: "earth".to_string(),
-            amount: 50u128.into(),
-        };
-        let product_quantity = 100u64;
+    let contribution = FUNDS
+        .may_load(deps.storage, (product_id.as_str(), &info.sender))?
+        .ok_or(ContractError::NoContribution {})?;
 
-        let msg = ExecuteMsg::RegisterProduct {
-            product_name,
-            product_price,
-            product_quantity,
-        };
-        let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    FUNDS.remove(deps.storage, (product_id.as_str(), &info.sender));
 
-        // Comprar todo o estoque
-        let buyer_info = mock_info("buyer", &coins(5000, "earth"));
-        let msg = ExecuteMsg::Buy {
-            product_id: "product-1".to_string(),
-            quantity: product_quantity,
-        };
-        let _res = execute(deps.as_mut(), mock_env(), buyer_info, msg).unwrap();
+    let refund_msg = BankMsg::Send {
+        to_address: info.sender.to_string(),
+        amount: vec![contribution.clone()],
+    };
+
+    Ok(Response::new()
+        .add_message(refund_msg)
+        .add_attribute("action", "refund")
+        .add_attribute("product_id", product_id)
+        .add_attribute("amount", contribution.amount.to_string()))
+}
+
+pub fn execute_join_purchase(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    product_id: String,
+    quantity: u64,
+) -> Result<Response, ContractError> {
+    let product = products().load(deps.storage, &product_id)?;
+
+    // Produtos em modo campanha só são vendidos via Fund/Claim, nunca por
+    // compra em grupo
+    if product.campaign.is_some() {
+        return Err(ContractError::ProductIsACampaign {});
+    }
+
+    if product.status == ProductStatus::Sold {
+        return Err(ContractError::ProductAlreadySold {});
+    }
+
+    let mut group = GROUPS
+        .may_load(deps.storage, &product_id)?
+        .unwrap_or(GroupPurchase {
+            quantity,
+            participants: vec![],
+        });
+
+    if group.quantity != quantity {
+        return Err(ContractError::QuantityMismatch {});
+    }
+
+    if !group.participants.contains(&info.sender) {
+        group.participants.push(info.sender.clone());
+    }
+    GROUPS.save(deps.storage, &product_id, &group)?;
+
+    // Registrar o depósito enviado por este participante como parte da sua cota
+    if !info.funds.is_empty() {
+        let sent = info
+            .funds
+            .iter()
+            .find(|coin| coin.denom == product.price.denom)
+            .map(|coin| coin.amount)
+            .unwrap_or(Uint128::zero());
+
+        let previous = GROUP_DEPOSITS
+            .may_load(deps.storage, (product_id.as_str(), &info.sender))?
+            .map(|coin| coin.amount)
+            .unwrap_or(Uint128::zero());
+        let deposited = math::add(previous, sent)?;
+        GROUP_DEPOSITS.save(
+            deps.storage,
+            (product_id.as_str(), &info.sender),
+            &Coin {
+                denom: product.price.denom.clone(),
+                amount: deposited,
+            },
+        )?;
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "join_purchase")
+        .add_attribute("product_id", product_id)
+        .add_attribute("participant", info.sender))
+}
+
+pub fn execute_settle_group(
+    deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    product_id: String,
+) -> Result<Response, ContractError> {
+    let mut product = products().load(deps.storage, &product_id)?;
+
+    if product.status == ProductStatus::Sold {
+        return Err(ContractError::ProductAlreadySold {});
+    }
+
+    let group = GROUPS
+        .may_load(deps.storage, &product_id)?
+        .ok_or(ContractError::NoGroupInProgress {})?;
+
+    let participant_count = group.participants.len();
+    if participant_count == 0 {
+        return Err(ContractError::EmptyGroup {});
+    }
+
+    let total_due = math::mul(product.price.amount, Uint128::from(group.quantity))?;
+
+    let head_count = Uint128::from(participant_count as u128);
+    let (per_head, remainder) = math::div_rem(total_due, head_count)?;
+
+    // Verificar se cada participante já depositou sua cota (o iniciador cobre o resto)
+    // e devolver qualquer valor depositado além da cota devida
+    let mut refund_msgs = Vec::new();
+    for (index, participant) in group.participants.iter().enumerate() {
+        let mut share_owed = per_head;
+        if index == 0 {
+            share_owed = math::add(share_owed, remainder)?;
+        }
+
+        let deposited = GROUP_DEPOSITS
+            .may_load(deps.storage, (product_id.as_str(), participant))?
+            .map(|coin| coin.amount)
+            .unwrap_or(Uint128::zero());
+
+        if deposited < share_owed {
+            return Err(ContractError::ShareNotDeposited {
+                participant: participant.to_string(),
+            });
+        }
+
+        let excess = math::sub(deposited, share_owed)?;
+        if !excess.is_zero() {
+            refund_msgs.push(BankMsg::Send {
+                to_address: participant.to_string(),
+                amount: vec![Coin {
+                    denom: product.price.denom.clone(),
+                    amount: excess,
+                }],
+            });
+        }
+    }
+
+    // Debitar o estoque e marcar o produto vendido se ele se esgotar
+    product.quantity = product.quantity.checked_sub(group.quantity).ok_or(
+        ContractError::InsufficientStock {
+            requested: group.quantity,
+            available: product.quantity,
+        },
+    )?;
+    if product.quantity == 0 {
+        product.status = ProductStatus::Sold;
+    }
+    products().save(deps.storage, &product_id, &product)?;
+
+    // Limpar o estado do grupo e os depósitos já liquidados
+    for participant in &group.participants {
+        GROUP_DEPOSITS.remove(deps.storage, (product_id.as_str(), participant));
+    }
+    GROUPS.remove(deps.storage, &product_id);
+
+    let payment_msg = BankMsg::Send {
+        to_address: product.owner.clone(),
+        amount: vec![Coin {
+            denom: product.price.denom.clone(),
+            amount: total_due,
+        }],
+    };
+
+    Ok(Response::new()
+        .add_message(payment_msg)
+        .add_messages(refund_msgs)
+        .add_attribute("action", "settle_group")
+        .add_attribute("product_id", product_id)
+        .add_attribute("quantity", group.quantity.to_string())
+        .add_attribute("total_paid", total_due.to_string()))
+}
+
+#[entry_point]
+pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::GetProducts {
+            start_after,
+            limit,
+            status,
+            owner,
+        } => query_products(deps, start_after, limit, status, owner),
+        QueryMsg::GetProductsByOwner {
+            owner,
+            start_after,
+            limit,
+        } => query_products_by_owner(deps, owner, start_after, limit),
+        QueryMsg::GetProductsByStatus {
+            status,
+            start_after,
+            limit,
+        } => query_products_by_status(deps, status, start_after, limit),
+        QueryMsg::GetProduct { id } => query_product(deps, id),
+        QueryMsg::GetFunders { product_id } => query_funders(deps, product_id),
+        QueryMsg::GetFunds { product_id } => query_funds(deps, product_id),
+        QueryMsg::GetGroup { product_id } => query_group(deps, product_id),
+    }
+}
+
+pub fn query_products(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+    status: Option<ProductStatus>,
+    owner: Option<String>,
+) -> StdResult<Binary> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let min = start_after.map(Bound::exclusive);
+
+    let products: StdResult<Vec<_>> = products()
+        .range(deps.storage, min, None, Order::Ascending)
+        .map(|item| {
+            let (_, product) = item?;
+            Ok(product)
+        })
+        .filter(|product: &StdResult<Product>| match product {
+            Ok(product) => {
+                status.as_ref().is_none_or(|s| &product.status == s)
+                    && owner.as_ref().is_none_or(|o| &product.owner == o)
+            }
+            Err(_) => true,
+        })
+        .take(limit)
+        .collect();
+    let products = products?;
+
+    let last_key = products.last().map(|product| product.id.clone());
+
+    to_binary(&ProductsResponse {
+        products,
+        last_key,
+    })
+}
+
+// Lista produtos de um dono específico usando o índice secundário `owner`,
+// em vez de varrer o catálogo inteiro e filtrar
+pub fn query_products_by_owner(
+    deps: Deps,
+    owner: String,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<Binary> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let min = start_after.map(Bound::exclusive);
+
+    let products: StdResult<Vec<_>> = products()
+        .idx
+        .owner
+        .prefix(owner.into_bytes())
+        .range(deps.storage, min, None, Order::Ascending)
+        .map(|item| {
+            let (_, product) = item?;
+            Ok(product)
+        })
+        .take(limit)
+        .collect();
+    let products = products?;
+
+    let last_key = products.last().map(|product| product.id.clone());
+
+    to_binary(&ProductsResponse {
+        products,
+        last_key,
+    })
+}
+
+// Lista produtos por status usando o índice secundário `status`,
+// em vez de varrer o catálogo inteiro e filtrar
+pub fn query_products_by_status(
+    deps: Deps,
+    status: ProductStatus,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<Binary> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let min = start_after.map(Bound::exclusive);
+
+    let products: StdResult<Vec<_>> = products()
+        .idx
+        .status
+        .prefix(product_status_bytes(&status))
+        .range(deps.storage, min, None, Order::Ascending)
+        .map(|item| {
+            let (_, product) = item?;
+            Ok(product)
+        })
+        .take(limit)
+        .collect();
+    let products = products?;
+
+    let last_key = products.last().map(|product| product.id.clone());
+
+    to_binary(&ProductsResponse {
+        products,
+        last_key,
+    })
+}
+
+pub fn query_product(deps: Deps, id: String) -> StdResult<Binary> {
+    let product = products().load(deps.storage, &id)?;
+    to_binary(&product)
+}
+
+pub fn query_funders(deps: Deps, product_id: String) -> StdResult<Binary> {
+    let funders: StdResult<Vec<_>> = FUNDS
+        .prefix(product_id.as_str())
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| {
+            let (raw_addr, amount) = item?;
+            let address = String::from_utf8(raw_addr)
+                .map_err(|_| StdError::generic_err("Invalid funder address in storage"))?;
+            Ok(FunderShare { address, amount })
+        })
+        .collect();
+
+    to_binary(&FundersResponse { funders: funders? })
+}
+
+pub fn query_funds(deps: Deps, product_id: String) -> StdResult<Binary> {
+    let product = products().load(deps.storage, &product_id)?;
+    let campaign = product
+        .campaign
+        .ok_or_else(|| StdError::generic_err("Product is not a campaign"))?;
+
+    to_binary(&FundsResponse {
+        goal: campaign.goal,
+        raised: campaign.raised,
+        deadline: campaign.deadline,
+    })
+}
+
+pub fn query_group(deps: Deps, product_id: String) -> StdResult<Binary> {
+    let product = products().load(deps.storage, &product_id)?;
+    let group = GROUPS
+        .may_load(deps.storage, &product_id)?
+        .ok_or_else(|| StdError::generic_err("No group purchase in progress for this product"))?;
+
+    let total_due = math::mul(product.price.amount, Uint128::from(group.quantity))
+        .map_err(|err| StdError::generic_err(err.to_string()))?;
+    let head_count = Uint128::from(group.participants.len() as u128);
+    let (per_head, remainder) =
+        math::div_rem(total_due, head_count).map_err(|err| StdError::generic_err(err.to_string()))?;
+
+    let participants = group
+        .participants
+        .iter()
+        .enumerate()
+        .map(|(index, participant)| {
+            let mut share_owed = per_head;
+            if index == 0 {
+                share_owed =
+                    math::add(share_owed, remainder).map_err(|err| StdError::generic_err(err.to_string()))?;
+            }
+            let deposited = GROUP_DEPOSITS
+                .may_load(deps.storage, (product_id.as_str(), participant))?
+                .map(|coin| coin.amount)
+                .unwrap_or(Uint128::zero());
+            Ok(GroupParticipantShare {
+                address: participant.to_string(),
+                share_owed,
+                deposited,
+            })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+
+    to_binary(&GroupResponse {
+        quantity: group.quantity,
+        participants,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
+    use cosmwasm_std::{coins, from_binary, CosmosMsg};
+
+    #[test]
+    fn proper_initialization() {
+        let mut deps = mock_dependencies(&[]);
+
+        let msg = InitMsg {};
+        let info = mock_info("creator", &coins(1000, "earth"));
+
+        // we can just call .unwrap() to assert this was a success
+        let res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert_eq!(0, res.messages.len());
+
+        // Verificar se o estado foi inicializado corretamente
+        let state = STATE.load(&deps.storage).unwrap();
+        assert_eq!(state.total_products, 0);
+
+        // Verificar se a versão do contrato foi registrada pelo cw2
+        let version = get_contract_version(&deps.storage).unwrap();
+        assert_eq!(version.contract, CONTRACT_NAME);
+        assert_eq!(version.version, CONTRACT_VERSION);
+    }
+
+    #[test]
+    fn migrate_rejects_foreign_contract() {
+        let mut deps = mock_dependencies(&[]);
+        set_contract_version(deps.as_mut().storage, "crates.io:some-other-contract", "1.0.0")
+            .unwrap();
+
+        let err = migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap_err();
+        match err {
+            StdError::GenericErr { msg, .. } => {
+                assert!(msg.contains("different contract"))
+            }
+            _ => panic!("expected a generic error for foreign contract migration"),
+        }
+    }
+
+    #[test]
+    fn migrate_rejects_downgrade() {
+        let mut deps = mock_dependencies(&[]);
+        set_contract_version(deps.as_mut().storage, CONTRACT_NAME, "999.0.0").unwrap();
+
+        let err = migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap_err();
+        match err {
+            StdError::GenericErr { msg, .. } => assert!(msg.contains("down to")),
+            _ => panic!("expected a generic error for a downgrade"),
+        }
+    }
+
+    #[test]
+    fn register_and_query_product() {
+        let mut deps = mock_dependencies(&[]);
+
+        // Inicializar o contrato
+        let msg = InitMsg {};
+        let info = mock_info("creator", &coins(1000, "earth"));
+        let _res = instantiate(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+
+        // Registrar um produto
+        let product_name = "Tomato".to_string();
+        let product_price = Coin {
+            denom: "earth".to_string(),
+            amount: 50u128.into(),
+        };
+        let product_quantity = 100u64;
+
+        let msg = ExecuteMsg::RegisterProduct {
+            product_name: product_name.clone(),
+            product_price: product_price.clone(),
+            product_quantity,
+            campaign: None,
+        };
+        let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // Verificar se o produto foi registrado
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::GetProducts {
+                start_after: None,
+                limit: None,
+                status: None,
+                owner: None,
+            },
+        )
+        .unwrap();
+        let products: ProductsResponse = from_binary(&res).unwrap();
+        
+        assert_eq!(products.products.len(), 1);
+        assert_eq!(products.products[0].name, product_name);
+        assert_eq!(products.products[0].price, product_price);
+        assert_eq!(products.products[0].quantity, product_quantity);
+        assert_eq!(products.products[0].status, ProductStatus::Available);
+    }
+
+    #[test]
+    fn buy_product() {
+        let mut deps = mock_dependencies(&[]);
+
+        // Inicializar o contrato
+        let msg = InitMsg {};
+        let info = mock_info("creator", &coins(1000, "earth"));
+        let _res = instantiate(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+
+        // Registrar um produto
+        let product_name = "Tomato".to_string();
+        let product_price = Coin {
+            denom: "earth".to_string(),
+            amount: 50u128.into(),
+        };
+        let product_quantity = 100u64;
+
+        let msg = ExecuteMsg::RegisterProduct {
+            product_name,
+            product_price,
+            product_quantity,
+            campaign: None,
+        };
+        let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // Comprar o produto (30 unidades a 50 "earth" cada)
+        let buyer_info = mock_info("buyer", &coins(1500, "earth"));
+        let buy_quantity = 30u64;
+        let msg = ExecuteMsg::Buy {
+            product_id: "product-0000000001".to_string(),
+            quantity: buy_quantity,
+        };
+        let res = execute(deps.as_mut(), mock_env(), buyer_info, msg).unwrap();
+
+        // Verificar se o pagamento foi repassado ao dono do produto
+        assert_eq!(res.messages.len(), 1);
+
+        // Verificar se a quantidade foi atualizada
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::GetProduct {
+                id: "product-0000000001".to_string(),
+            },
+        )
+        .unwrap();
+        let product: Product = from_binary(&res).unwrap();
+        
+        assert_eq!(product.quantity, product_quantity - buy_quantity);
+        assert_eq!(product.status, ProductStatus::Available);
+    }
+
+    #[test]
+    fn buy_all_stock() {
+        let mut deps = mock_dependencies(&[]);
+
+        // Inicializar o contrato
+        let msg = InitMsg {};
+        let info = mock_info("creator", &coins(1000, "earth"));
+        let _res = instantiate(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+
+        // Registrar um produto
+        let product_name = "Tomato".to_string();
+        let product_price = Coin {
+            denom: "earth".to_string(),
+            amount: 50u128.into(),
+        };
+        let product_quantity = 100u64;
+
+        let msg = ExecuteMsg::RegisterProduct {
+            product_name,
+            product_price,
+            product_quantity,
+            campaign: None,
+        };
+        let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // Comprar todo o estoque
+        let buyer_info = mock_info("buyer", &coins(5000, "earth"));
+        let msg = ExecuteMsg::Buy {
+            product_id: "product-0000000001".to_string(),
+            quantity: product_quantity,
+        };
+        let _res = execute(deps.as_mut(), mock_env(), buyer_info, msg).unwrap();
 
         // Verificar se o produto está marcado como vendido
         let res = query(
             deps.as_ref(),
             mock_env(),
             QueryMsg::GetProduct {
-                id: "product-1".to_string(),
+                id: "product-0000000001".to_string(),
+            },
+        )
+        .unwrap();
+        let product: Product = from_binary(&res).unwrap();
+        
+        assert_eq!(product.quantity, 0);
+        assert_eq!(product.status, ProductStatus::Sold);
+    }
+
+    #[test]
+    fn buy_product_with_wrong_payment_fails() {
+        let mut deps = mock_dependencies(&[]);
+
+        // Inicializar o contrato
+        let msg = InitMsg {};
+        let info = mock_info("creator", &coins(1000, "earth"));
+        let _res = instantiate(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+
+        // Registrar um produto
+        let product_name = "Tomato".to_string();
+        let product_price = Coin {
+            denom: "earth".to_string(),
+            amount: 50u128.into(),
+        };
+        let product_quantity = 100u64;
+
+        let msg = ExecuteMsg::RegisterProduct {
+            product_name,
+            product_price,
+            product_quantity,
+            campaign: None,
+        };
+        let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // Tentar comprar sem enviar o valor correto deve falhar
+        let buyer_info = mock_info("buyer", &coins(10, "earth"));
+        let msg = ExecuteMsg::Buy {
+            product_id: "product-0000000001".to_string(),
+            quantity: 30u64,
+        };
+        let err = execute(deps.as_mut(), mock_env(), buyer_info, msg).unwrap_err();
+
+        assert!(matches!(err, ContractError::WrongPayment { .. }));
+    }
+
+    #[test]
+    fn buy_campaign_product_fails() {
+        let mut deps = mock_dependencies(&[]);
+
+        // Inicializar o contrato
+        let msg = InitMsg {};
+        let info = mock_info("creator", &coins(1000, "earth"));
+        let _res = instantiate(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+
+        // Registrar uma campanha de crowdfunding
+        let product_price = Coin {
+            denom: "earth".to_string(),
+            amount: 50u128.into(),
+        };
+        let msg = ExecuteMsg::RegisterProduct {
+            product_name: "Tractor".to_string(),
+            product_price,
+            product_quantity: 100u64,
+            campaign: Some(CampaignInit {
+                goal: Uint128::new(1000),
+                deadline: mock_env().block.height + 100,
+            }),
+        };
+        let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // Comprar diretamente uma campanha deve falhar: o único caminho é Fund/Claim
+        let buyer_info = mock_info("buyer", &coins(5000, "earth"));
+        let msg = ExecuteMsg::Buy {
+            product_id: "product-0000000001".to_string(),
+            quantity: 1u64,
+        };
+        let err = execute(deps.as_mut(), mock_env(), buyer_info, msg).unwrap_err();
+
+        assert!(matches!(err, ContractError::ProductIsACampaign {}));
+    }
+
+    #[test]
+    fn join_purchase_campaign_product_fails() {
+        let mut deps = mock_dependencies(&[]);
+
+        // Inicializar o contrato
+        let msg = InitMsg {};
+        let info = mock_info("creator", &coins(1000, "earth"));
+        let _res = instantiate(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+
+        // Registrar uma campanha de crowdfunding
+        let product_price = Coin {
+            denom: "earth".to_string(),
+            amount: 50u128.into(),
+        };
+        let msg = ExecuteMsg::RegisterProduct {
+            product_name: "Tractor".to_string(),
+            product_price,
+            product_quantity: 100u64,
+            campaign: Some(CampaignInit {
+                goal: Uint128::new(100_000),
+                deadline: mock_env().block.height + 100,
+            }),
+        };
+        let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // Entrar em uma compra em grupo também deve falhar: o único caminho é Fund/Claim
+        let participant_info = mock_info("p1", &coins(50, "earth"));
+        let msg = ExecuteMsg::JoinPurchase {
+            product_id: "product-0000000001".to_string(),
+            quantity: 1u64,
+        };
+        let err = execute(deps.as_mut(), mock_env(), participant_info, msg).unwrap_err();
+
+        assert!(matches!(err, ContractError::ProductIsACampaign {}));
+    }
+
+    #[test]
+    fn fund_campaign_and_claim_when_goal_met() {
+        let mut deps = mock_dependencies(&[]);
+
+        let msg = InitMsg {};
+        let info = mock_info("creator", &coins(1000, "earth"));
+        let _res = instantiate(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+
+        // Registrar uma campanha: meta de 1000 "earth", fechando no bloco 12_400
+        let product_price = Coin {
+            denom: "earth".to_string(),
+            amount: 50u128.into(),
+        };
+        let msg = ExecuteMsg::RegisterProduct {
+            product_name: "Tractor".to_string(),
+            product_price,
+            product_quantity: 100u64,
+            campaign: Some(CampaignInit {
+                goal: Uint128::new(1000),
+                deadline: 12_400,
+            }),
+        };
+        let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // Dois financiadores cobrem a meta juntos (20 + 10 unidades a 50 "earth");
+        // a tentativa de financiar 20 unidades enviando só 500 é rejeitada
+        let funder_one = mock_info("funder1", &coins(1000, "earth"));
+        let msg = ExecuteMsg::Fund {
+            product_id: "product-0000000001".to_string(),
+            quantity: 20,
+        };
+        let _res = execute(deps.as_mut(), mock_env(), funder_one, msg).unwrap();
+
+        let funder_two = mock_info("funder2", &coins(500, "earth"));
+        let msg = ExecuteMsg::Fund {
+            product_id: "product-0000000001".to_string(),
+            quantity: 20,
+        };
+        let err = execute(deps.as_mut(), mock_env(), funder_two.clone(), msg).unwrap_err();
+        assert!(matches!(err, ContractError::WrongPayment { .. }));
+        let msg = ExecuteMsg::Fund {
+            product_id: "product-0000000001".to_string(),
+            quantity: 10,
+        };
+        let _res = execute(deps.as_mut(), mock_env(), funder_two, msg).unwrap();
+
+        // GetFunders lista a contribuição de cada financiador
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::GetFunders {
+                product_id: "product-0000000001".to_string(),
+            },
+        )
+        .unwrap();
+        let funders: FundersResponse = from_binary(&res).unwrap();
+        assert_eq!(funders.funders.len(), 2);
+        assert_eq!(funders.funders[0].address, "funder1");
+        assert_eq!(funders.funders[0].amount.amount, Uint128::new(1000));
+        assert_eq!(funders.funders[1].address, "funder2");
+        assert_eq!(funders.funders[1].amount.amount, Uint128::new(500));
+
+        // GetFunds resume o progresso da campanha
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::GetFunds {
+                product_id: "product-0000000001".to_string(),
+            },
+        )
+        .unwrap();
+        let funds: FundsResponse = from_binary(&res).unwrap();
+        assert_eq!(funds.goal, Uint128::new(1000));
+        assert_eq!(funds.raised, Uint128::new(1500));
+        assert_eq!(funds.deadline, 12_400);
+
+        // Antes do prazo, o dono ainda não pode reivindicar
+        let mut env = mock_env();
+        let owner_info = mock_info("creator", &[]);
+        let msg = ExecuteMsg::Claim {
+            product_id: "product-0000000001".to_string(),
+        };
+        let err = execute(deps.as_mut(), env.clone(), owner_info.clone(), msg).unwrap_err();
+        assert!(matches!(err, ContractError::DeadlineNotReached {}));
+
+        // Após o prazo, com a meta atingida, o dono reivindica os fundos
+        env.block.height = 12_400;
+        let msg = ExecuteMsg::Claim {
+            product_id: "product-0000000001".to_string(),
+        };
+        let res = execute(deps.as_mut(), env, owner_info, msg).unwrap();
+        assert_eq!(res.messages.len(), 1);
+
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::GetProduct {
+                id: "product-0000000001".to_string(),
             },
         )
         .unwrap();
         let product: Product = from_binary(&res).unwrap();
-        
-        assert_eq!(product.quantity, 0);
         assert_eq!(product.status, ProductStatus::Sold);
+        assert_eq!(product.quantity, 100 - 30);
+    }
+
+    #[test]
+    fn fund_rejects_when_exceeding_stock() {
+        let mut deps = mock_dependencies(&[]);
+
+        let msg = InitMsg {};
+        let info = mock_info("creator", &coins(1000, "earth"));
+        let _res = instantiate(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+
+        // Campanha com apenas 10 unidades em estoque
+        let product_price = Coin {
+            denom: "earth".to_string(),
+            amount: 50u128.into(),
+        };
+        let msg = ExecuteMsg::RegisterProduct {
+            product_name: "Tractor".to_string(),
+            product_price,
+            product_quantity: 10u64,
+            campaign: Some(CampaignInit {
+                goal: Uint128::new(1000),
+                deadline: 12_400,
+            }),
+        };
+        let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // Financiar mais unidades do que o estoque disponível deve falhar
+        let funder = mock_info("funder1", &coins(550, "earth"));
+        let msg = ExecuteMsg::Fund {
+            product_id: "product-0000000001".to_string(),
+            quantity: 11,
+        };
+        let err = execute(deps.as_mut(), mock_env(), funder, msg).unwrap_err();
+        assert!(matches!(err, ContractError::InsufficientStock { .. }));
+    }
+
+    #[test]
+    fn refund_campaign_when_goal_not_met() {
+        let mut deps = mock_dependencies(&[]);
+
+        let msg = InitMsg {};
+        let info = mock_info("creator", &coins(1000, "earth"));
+        let _res = instantiate(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+
+        // Registrar uma campanha: meta de 1000 "earth", fechando no bloco 12_400
+        let product_price = Coin {
+            denom: "earth".to_string(),
+            amount: 50u128.into(),
+        };
+        let msg = ExecuteMsg::RegisterProduct {
+            product_name: "Tractor".to_string(),
+            product_price,
+            product_quantity: 100u64,
+            campaign: Some(CampaignInit {
+                goal: Uint128::new(1000),
+                deadline: 12_400,
+            }),
+        };
+        let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // Apenas um financiador, bem abaixo da meta
+        let funder = mock_info("funder1", &coins(100, "earth"));
+        let msg = ExecuteMsg::Fund {
+            product_id: "product-0000000001".to_string(),
+            quantity: 2,
+        };
+        let _res = execute(deps.as_mut(), mock_env(), funder.clone(), msg).unwrap();
+
+        // Após o prazo, com a meta não atingida, o financiador recupera os fundos
+        let mut env = mock_env();
+        env.block.height = 12_400;
+        let msg = ExecuteMsg::Refund {
+            product_id: "product-0000000001".to_string(),
+        };
+        let res = execute(deps.as_mut(), env, funder, msg).unwrap();
+        assert_eq!(res.messages.len(), 1);
+    }
+
+    #[test]
+    fn settle_group_fails_when_deposits_are_insufficient() {
+        let mut deps = mock_dependencies(&[]);
+
+        let msg = InitMsg {};
+        let info = mock_info("creator", &coins(1000, "earth"));
+        let _res = instantiate(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+
+        let product_price = Coin {
+            denom: "earth".to_string(),
+            amount: 50u128.into(),
+        };
+        let msg = ExecuteMsg::RegisterProduct {
+            product_name: "Tomato".to_string(),
+            product_price,
+            product_quantity: 10u64,
+            campaign: None,
+        };
+        let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // O participante 1 entra no grupo, mas deposita menos do que sua cota
+        let p1 = mock_info("p1", &coins(10, "earth"));
+        let msg = ExecuteMsg::JoinPurchase {
+            product_id: "product-0000000001".to_string(),
+            quantity: 1,
+        };
+        let _res = execute(deps.as_mut(), mock_env(), p1, msg).unwrap();
+
+        let p2 = mock_info("p2", &[]);
+        let msg = ExecuteMsg::SettleGroup {
+            product_id: "product-0000000001".to_string(),
+        };
+        let err = execute(deps.as_mut(), mock_env(), p2, msg).unwrap_err();
+        assert!(matches!(err, ContractError::ShareNotDeposited { .. }));
+    }
+
+    #[test]
+    fn join_purchase_and_settle_group_splits_cost_with_remainder() {
+        let mut deps = mock_dependencies(&[]);
+
+        let msg = InitMsg {};
+        let info = mock_info("creator", &coins(1000, "earth"));
+        let _res = instantiate(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+
+        // Preço 50: dividido por 3 participantes dá 16 cada, com resto 2 para o iniciador
+        let product_price = Coin {
+            denom: "earth".to_string(),
+            amount: 50u128.into(),
+        };
+        let msg = ExecuteMsg::RegisterProduct {
+            product_name: "Tomato".to_string(),
+            product_price,
+            product_quantity: 10u64,
+            campaign: None,
+        };
+        let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        for (sender, amount) in [("p1", 18u128), ("p2", 16), ("p3", 16)] {
+            let info = mock_info(sender, &coins(amount, "earth"));
+            let msg = ExecuteMsg::JoinPurchase {
+                product_id: "product-0000000001".to_string(),
+                quantity: 1,
+            };
+            let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        }
+
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::GetGroup {
+                product_id: "product-0000000001".to_string(),
+            },
+        )
+        .unwrap();
+        let group: GroupResponse = from_binary(&res).unwrap();
+        assert_eq!(group.participants.len(), 3);
+        assert_eq!(group.participants[0].share_owed, Uint128::new(18));
+        assert_eq!(group.participants[1].share_owed, Uint128::new(16));
+
+        let settler = mock_info("anyone", &[]);
+        let msg = ExecuteMsg::SettleGroup {
+            product_id: "product-0000000001".to_string(),
+        };
+        let res = execute(deps.as_mut(), mock_env(), settler, msg).unwrap();
+        assert_eq!(res.messages.len(), 1);
+
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::GetProduct {
+                id: "product-0000000001".to_string(),
+            },
+        )
+        .unwrap();
+        let product: Product = from_binary(&res).unwrap();
+        assert_eq!(product.quantity, 9);
+    }
+
+    #[test]
+    fn settle_group_refunds_overpaid_share() {
+        let mut deps = mock_dependencies(&[]);
+
+        let msg = InitMsg {};
+        let info = mock_info("creator", &coins(1000, "earth"));
+        let _res = instantiate(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+
+        // Preço 50: dividido por 2 participantes dá 25 cada
+        let product_price = Coin {
+            denom: "earth".to_string(),
+            amount: 50u128.into(),
+        };
+        let msg = ExecuteMsg::RegisterProduct {
+            product_name: "Tomato".to_string(),
+            product_price,
+            product_quantity: 10u64,
+            campaign: None,
+        };
+        let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // p1 deposita exatamente sua cota, p2 deposita além da sua cota
+        for (sender, amount) in [("p1", 25u128), ("p2", 40)] {
+            let info = mock_info(sender, &coins(amount, "earth"));
+            let msg = ExecuteMsg::JoinPurchase {
+                product_id: "product-0000000001".to_string(),
+                quantity: 1,
+            };
+            let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        }
+
+        let settler = mock_info("anyone", &[]);
+        let msg = ExecuteMsg::SettleGroup {
+            product_id: "product-0000000001".to_string(),
+        };
+        let res = execute(deps.as_mut(), mock_env(), settler, msg).unwrap();
+
+        // Uma mensagem para o dono e uma de devolução do excedente de p2
+        assert_eq!(res.messages.len(), 2);
+        let refund = res
+            .messages
+            .iter()
+            .find_map(|sub_msg| match &sub_msg.msg {
+                CosmosMsg::Bank(BankMsg::Send { to_address, amount }) if to_address == "p2" => {
+                    Some(amount.clone())
+                }
+                _ => None,
+            })
+            .expect("p2 should receive a refund of their excess deposit");
+        assert_eq!(refund, vec![Coin::new(15, "earth")]);
+    }
+
+    #[test]
+    fn get_products_paginates_and_filters() {
+        let mut deps = mock_dependencies(&[]);
+
+        let msg = InitMsg {};
+        let info = mock_info("creator", &coins(1000, "earth"));
+        let _res = instantiate(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+
+        // Registrar três produtos de dois donos diferentes
+        for (owner, name) in [
+            ("creator", "Tomato"),
+            ("creator", "Potato"),
+            ("other", "Carrot"),
+        ] {
+            let msg = ExecuteMsg::RegisterProduct {
+                product_name: name.to_string(),
+                product_price: Coin {
+                    denom: "earth".to_string(),
+                    amount: 50u128.into(),
+                },
+                product_quantity: 10,
+                campaign: None,
+            };
+            let sender = mock_info(owner, &[]);
+            let _res = execute(deps.as_mut(), mock_env(), sender, msg).unwrap();
+        }
+
+        // Paginar duas de cada vez
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::GetProducts {
+                start_after: None,
+                limit: Some(2),
+                status: None,
+                owner: None,
+            },
+        )
+        .unwrap();
+        let page: ProductsResponse = from_binary(&res).unwrap();
+        assert_eq!(page.products.len(), 2);
+        assert_eq!(page.last_key, Some("product-0000000002".to_string()));
+
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::GetProducts {
+                start_after: page.last_key,
+                limit: Some(2),
+                status: None,
+                owner: None,
+            },
+        )
+        .unwrap();
+        let page: ProductsResponse = from_binary(&res).unwrap();
+        assert_eq!(page.products.len(), 1);
+        assert_eq!(page.products[0].name, "Carrot");
+
+        // Filtrar por dono
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::GetProducts {
+                start_after: None,
+                limit: None,
+                status: None,
+                owner: Some("creator".to_string()),
+            },
+        )
+        .unwrap();
+        let filtered: ProductsResponse = from_binary(&res).unwrap();
+        assert_eq!(filtered.products.len(), 2);
+    }
+
+    #[test]
+    fn get_products_by_owner_and_status_use_indexes() {
+        let mut deps = mock_dependencies(&[]);
+
+        let msg = InitMsg {};
+        let info = mock_info("creator", &coins(1000, "earth"));
+        let _res = instantiate(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+
+        // Registrar dois produtos de "creator" e um de "other"
+        for (owner, name) in [
+            ("creator", "Tomato"),
+            ("creator", "Potato"),
+            ("other", "Carrot"),
+        ] {
+            let msg = ExecuteMsg::RegisterProduct {
+                product_name: name.to_string(),
+                product_price: Coin {
+                    denom: "earth".to_string(),
+                    amount: 50u128.into(),
+                },
+                product_quantity: 10,
+                campaign: None,
+            };
+            let sender = mock_info(owner, &[]);
+            let _res = execute(deps.as_mut(), mock_env(), sender, msg).unwrap();
+        }
+
+        // O índice por dono só deve trazer os produtos de "creator"
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::GetProductsByOwner {
+                owner: "creator".to_string(),
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap();
+        let by_owner: ProductsResponse = from_binary(&res).unwrap();
+        assert_eq!(by_owner.products.len(), 2);
+        assert!(by_owner.products.iter().all(|p| p.owner == "creator"));
+
+        // Vender todo o estoque de "product-0000000001" para que ele mude de status
+        let buyer_info = mock_info("buyer", &coins(500, "earth"));
+        let msg = ExecuteMsg::Buy {
+            product_id: "product-0000000001".to_string(),
+            quantity: 10,
+        };
+        let _res = execute(deps.as_mut(), mock_env(), buyer_info, msg).unwrap();
+
+        // O índice por status deve refletir a venda
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::GetProductsByStatus {
+                status: ProductStatus::Sold,
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap();
+        let sold: ProductsResponse = from_binary(&res).unwrap();
+        assert_eq!(sold.products.len(), 1);
+        assert_eq!(sold.products[0].id, "product-0000000001");
+
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::GetProductsByStatus {
+                status: ProductStatus::Available,
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap();
+        let available: ProductsResponse = from_binary(&res).unwrap();
+        assert_eq!(available.products.len(), 2);
     }
 }
\ No newline at end of file
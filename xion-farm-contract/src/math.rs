@@ -0,0 +1,25 @@
+use cosmwasm_std::Uint128;
+
+use crate::error::ContractError;
+
+// Operações checadas sobre Uint128, usadas pelos handlers de execução nas contas
+// de preço, fundos e cotas (Buy, Fund, JoinPurchase, SettleGroup) para que overflow e divisão por zero virem um ContractError em vez de panic
+
+pub fn add(a: Uint128, b: Uint128) -> Result<Uint128, ContractError> {
+    Ok(a.checked_add(b)?)
+}
+
+pub fn sub(a: Uint128, b: Uint128) -> Result<Uint128, ContractError> {
+    Ok(a.checked_sub(b)?)
+}
+
+pub fn mul(a: Uint128, b: Uint128) -> Result<Uint128, ContractError> {
+    Ok(a.checked_mul(b)?)
+}
+
+// Divide `a` por `b`, retornando o quociente e o resto da divisão inteira
+pub fn div_rem(a: Uint128, b: Uint128) -> Result<(Uint128, Uint128), ContractError> {
+    let quotient = a.checked_div(b)?;
+    let remainder = sub(a, mul(quotient, b)?)?;
+    Ok((quotient, remainder))
+}